@@ -0,0 +1,473 @@
+use crate::error::{BoxError, Code, ScrapeError};
+use async_compression::tokio::write::{BrotliEncoder, GzipEncoder, ZstdEncoder};
+use log::{error, trace};
+use serde_json::Value;
+use tokio::io::AsyncWriteExt;
+
+/// Compression to apply to each bulk-import chunk before it goes over the
+/// wire, with the matching `Content-Encoding` header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+    Brotli,
+}
+
+impl Compression {
+    fn content_encoding(&self) -> Option<&'static str> {
+        match self {
+            Compression::None => None,
+            Compression::Gzip => Some("gzip"),
+            Compression::Zstd => Some("zstd"),
+            Compression::Brotli => Some("br"),
+        }
+    }
+
+    async fn compress(&self, body: Vec<u8>) -> std::io::Result<Vec<u8>> {
+        match self {
+            Compression::None => Ok(body),
+            Compression::Gzip => {
+                let mut encoder = GzipEncoder::new(Vec::new());
+                encoder.write_all(&body).await?;
+                encoder.shutdown().await?;
+                Ok(encoder.into_inner())
+            }
+            Compression::Zstd => {
+                let mut encoder = ZstdEncoder::new(Vec::new());
+                encoder.write_all(&body).await?;
+                encoder.shutdown().await?;
+                Ok(encoder.into_inner())
+            }
+            Compression::Brotli => {
+                let mut encoder = BrotliEncoder::new(Vec::new());
+                encoder.write_all(&body).await?;
+                encoder.shutdown().await?;
+                Ok(encoder.into_inner())
+            }
+        }
+    }
+}
+
+impl Default for Compression {
+    fn default() -> Self {
+        Compression::None
+    }
+}
+
+/// Controls how `bulk_index` batches and compresses documents: at most
+/// `chunk_size` documents per request, each optionally compressed.
+#[derive(Debug, Clone, Copy)]
+pub struct BulkOptions {
+    pub chunk_size: usize,
+    pub compression: Compression,
+}
+
+impl Default for BulkOptions {
+    fn default() -> Self {
+        BulkOptions {
+            chunk_size: 1000,
+            compression: Compression::None,
+        }
+    }
+}
+
+/// Per-query knobs that apply across backends: typo tolerance, paging, and
+/// (where the backend supports it) per-field boosts.
+#[derive(Debug, Clone)]
+pub struct QueryOptions {
+    pub fuzzy: bool,
+    pub from: usize,
+    pub size: usize,
+    pub boosts: Option<std::collections::HashMap<String, f64>>,
+}
+
+impl Default for QueryOptions {
+    fn default() -> Self {
+        QueryOptions {
+            fuzzy: false,
+            from: 0,
+            size: 10,
+            boosts: None,
+        }
+    }
+}
+
+/// Common surface for a search engine backend: create an index, bulk-load
+/// documents into it, and run a query against it. Each backend is
+/// responsible for translating `query` into its own wire format.
+#[async_trait::async_trait]
+pub trait SearchBackend: Send + Sync {
+    async fn create_index(&self, name: &str, settings: &Value) -> Result<(), BoxError>;
+    async fn bulk_index(&self, name: &str, docs: &[Value]) -> Result<(), BoxError>;
+    async fn search(
+        &self,
+        name: &str,
+        query: &str,
+        options: &QueryOptions,
+    ) -> Result<Value, BoxError>;
+}
+
+/// Elasticsearch backend: index settings via `PUT /{name}`, documents via
+/// the newline-delimited `_bulk` format, and queries via `GET /{name}/_search`.
+pub struct ElasticBackend {
+    pub base_url: String,
+    client: reqwest::Client,
+    bulk_options: BulkOptions,
+}
+
+impl ElasticBackend {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        ElasticBackend {
+            base_url: base_url.into(),
+            client: reqwest::Client::new(),
+            bulk_options: BulkOptions::default(),
+        }
+    }
+
+    pub fn with_bulk_options(mut self, bulk_options: BulkOptions) -> Self {
+        self.bulk_options = bulk_options;
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl SearchBackend for ElasticBackend {
+    async fn create_index(&self, name: &str, settings: &Value) -> Result<(), BoxError> {
+        let endpoint = format!("{}/{}", self.base_url, name);
+        trace!("Creating index {}", endpoint);
+        let resp = self
+            .client
+            .put(&endpoint)
+            .header("Content-Type", "application/json")
+            .json(settings)
+            .send()
+            .await?;
+        if resp.status().is_success() {
+            trace!("Index {} successfully created", endpoint);
+            Ok(())
+        } else {
+            let status = resp.status();
+            let resp_status = String::from(status.as_str());
+            let resp_msg = resp.text().await.expect("Response");
+            error!(
+                "Index '{}' creation failed with status {}: {}",
+                name, resp_status, resp_msg
+            );
+            Err(Box::new(ScrapeError::new(
+                Code::from_index_response(status),
+                format!("index '{}' failure: status {}: {}", name, resp_status, resp_msg),
+            )))
+        }
+    }
+
+    async fn bulk_index(&self, name: &str, docs: &[Value]) -> Result<(), BoxError> {
+        let endpoint = format!("{}/{}/_doc/_bulk", self.base_url, name);
+        let chunk_size = self.bulk_options.chunk_size.max(1);
+        for chunk in docs.chunks(chunk_size) {
+            trace!(
+                "Importing bulk chunk of {} documents to {}",
+                chunk.len(),
+                endpoint
+            );
+            let mut body = String::new();
+            for doc in chunk {
+                let id = uuid::Uuid::new_v4();
+                body.push_str(&format!(
+                    "{{ \"index\": {{ \"_index\": \"{}\", \"_type\": \"_doc\", \"_id\": \"{}\" }} }}\n",
+                    name, id
+                ));
+                body.push_str(&serde_json::to_string(doc)?);
+                body.push('\n');
+            }
+            let body = self.bulk_options.compression.compress(body.into_bytes()).await?;
+
+            let mut request = self
+                .client
+                .put(&endpoint)
+                .header("Content-Type", "application/json");
+            if let Some(encoding) = self.bulk_options.compression.content_encoding() {
+                request = request.header("Content-Encoding", encoding);
+            }
+            let resp = request.body(body).send().await?;
+            if !resp.status().is_success() {
+                let status = resp.status();
+                let resp_status = String::from(status.as_str());
+                let resp_msg = resp.text().await.expect("Response");
+                error!(
+                    "Bulk import {} failed with status {}: {}",
+                    name, resp_status, resp_msg
+                );
+                return Err(Box::new(ScrapeError::new(
+                    Code::from_bulk_response(status),
+                    format!("bulk import '{}' failure: status {}: {}", name, resp_status, resp_msg),
+                )));
+            }
+        }
+        trace!("Dataset successfully imported");
+        Ok(())
+    }
+
+    async fn search(
+        &self,
+        name: &str,
+        query: &str,
+        options: &QueryOptions,
+    ) -> Result<Value, BoxError> {
+        let endpoint = format!("{}/{}/_search", self.base_url, name);
+        trace!("Searching endpoint {}", endpoint);
+        let json = crate::build_query(query, options)?;
+        let resp = self.client.get(&endpoint).json(&json).send().await?;
+        if resp.status().is_success() {
+            trace!("Dataset successfully searched");
+            let ret = resp.json::<Value>().await?;
+            Ok(ret)
+        } else {
+            let status = resp.status();
+            let resp_status = String::from(status.as_str());
+            let resp_msg = resp.text().await.expect("Response");
+            error!(
+                "Dataset search failed with status {}: {}",
+                resp_status, resp_msg
+            );
+            Err(Box::new(ScrapeError::new(
+                Code::from_search_response(status),
+                format!("search on '{}' failed: status {}: {}", name, resp_status, resp_msg),
+            )))
+        }
+    }
+}
+
+/// MeiliSearch backend: settings via `POST /indexes/{name}/settings`,
+/// documents as a plain JSON array via `POST /indexes/{name}/documents`,
+/// and queries via `POST /indexes/{name}/search` with `{"q": query}`.
+pub struct MeiliBackend {
+    pub base_url: String,
+    client: reqwest::Client,
+    bulk_options: BulkOptions,
+}
+
+impl MeiliBackend {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        MeiliBackend {
+            base_url: base_url.into(),
+            client: reqwest::Client::new(),
+            bulk_options: BulkOptions::default(),
+        }
+    }
+
+    pub fn with_bulk_options(mut self, bulk_options: BulkOptions) -> Self {
+        self.bulk_options = bulk_options;
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl SearchBackend for MeiliBackend {
+    async fn create_index(&self, name: &str, settings: &Value) -> Result<(), BoxError> {
+        let endpoint = format!("{}/indexes/{}/settings", self.base_url, name);
+        trace!("Creating index {}", endpoint);
+        let resp = self
+            .client
+            .post(&endpoint)
+            .header("Content-Type", "application/json")
+            .json(settings)
+            .send()
+            .await?;
+        if resp.status().is_success() {
+            trace!("Index {} successfully created", endpoint);
+            Ok(())
+        } else {
+            let status = resp.status();
+            let resp_status = String::from(status.as_str());
+            let resp_msg = resp.text().await.expect("Response");
+            error!(
+                "Index '{}' creation failed with status {}: {}",
+                name, resp_status, resp_msg
+            );
+            Err(Box::new(ScrapeError::new(
+                Code::from_index_response(status),
+                format!("index '{}' failure: status {}: {}", name, resp_status, resp_msg),
+            )))
+        }
+    }
+
+    async fn bulk_index(&self, name: &str, docs: &[Value]) -> Result<(), BoxError> {
+        let endpoint = format!("{}/indexes/{}/documents", self.base_url, name);
+        let chunk_size = self.bulk_options.chunk_size.max(1);
+        for chunk in docs.chunks(chunk_size) {
+            trace!(
+                "Importing bulk chunk of {} documents to {}",
+                chunk.len(),
+                endpoint
+            );
+            let body = serde_json::to_vec(chunk)?;
+            let body = self.bulk_options.compression.compress(body).await?;
+
+            let mut request = self
+                .client
+                .post(&endpoint)
+                .header("Content-Type", "application/json");
+            if let Some(encoding) = self.bulk_options.compression.content_encoding() {
+                request = request.header("Content-Encoding", encoding);
+            }
+            let resp = request.body(body).send().await?;
+            if !resp.status().is_success() {
+                let status = resp.status();
+                let resp_status = String::from(status.as_str());
+                let resp_msg = resp.text().await.expect("Response");
+                error!(
+                    "Bulk import {} failed with status {}: {}",
+                    name, resp_status, resp_msg
+                );
+                return Err(Box::new(ScrapeError::new(
+                    Code::from_bulk_response(status),
+                    format!("bulk import '{}' failure: status {}: {}", name, resp_status, resp_msg),
+                )));
+            }
+        }
+        trace!("Dataset successfully imported");
+        Ok(())
+    }
+
+    async fn search(
+        &self,
+        name: &str,
+        query: &str,
+        options: &QueryOptions,
+    ) -> Result<Value, BoxError> {
+        // MeiliSearch is typo-tolerant and field-boosted by its own ranking
+        // rules, so only paging carries over from `options` here.
+        let endpoint = format!("{}/indexes/{}/search", self.base_url, name);
+        trace!("Searching endpoint {}", endpoint);
+        let resp = self
+            .client
+            .post(&endpoint)
+            .json(&serde_json::json!({
+                "q": query,
+                "limit": options.size,
+                "offset": options.from,
+            }))
+            .send()
+            .await?;
+        if resp.status().is_success() {
+            trace!("Dataset successfully searched");
+            let ret = resp.json::<Value>().await?;
+            Ok(ret)
+        } else {
+            let status = resp.status();
+            let resp_status = String::from(status.as_str());
+            let resp_msg = resp.text().await.expect("Response");
+            error!(
+                "Dataset search failed with status {}: {}",
+                resp_status, resp_msg
+            );
+            Err(Box::new(ScrapeError::new(
+                Code::from_search_response(status),
+                format!("search on '{}' failed: status {}: {}", name, resp_status, resp_msg),
+            )))
+        }
+    }
+}
+
+/// Which engine to talk to, selected via the `--backend` flag or
+/// `SEARCH_BACKEND` env var.
+pub enum BackendKind {
+    Elastic,
+    Meili,
+}
+
+impl BackendKind {
+    pub fn from_str(s: &str) -> BackendKind {
+        match s {
+            "meili" | "meilisearch" => BackendKind::Meili,
+            _ => BackendKind::Elastic,
+        }
+    }
+}
+
+pub fn make_backend(kind: BackendKind, base_url: &str, bulk_options: BulkOptions) -> Box<dyn SearchBackend> {
+    match kind {
+        BackendKind::Elastic => Box::new(ElasticBackend::new(base_url).with_bulk_options(bulk_options)),
+        BackendKind::Meili => Box::new(MeiliBackend::new(base_url).with_bulk_options(bulk_options)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_compression::tokio::write::{BrotliDecoder, GzipDecoder, ZstdDecoder};
+
+    async fn assert_round_trips(compression: Compression) {
+        let body = b"the quick brown fox jumps over the lazy dog".repeat(20);
+        let compressed = compression.compress(body.clone()).await.unwrap();
+
+        let decompressed = match compression {
+            Compression::None => compressed.clone(),
+            Compression::Gzip => {
+                let mut decoder = GzipDecoder::new(Vec::new());
+                decoder.write_all(&compressed).await.unwrap();
+                decoder.shutdown().await.unwrap();
+                decoder.into_inner()
+            }
+            Compression::Zstd => {
+                let mut decoder = ZstdDecoder::new(Vec::new());
+                decoder.write_all(&compressed).await.unwrap();
+                decoder.shutdown().await.unwrap();
+                decoder.into_inner()
+            }
+            Compression::Brotli => {
+                let mut decoder = BrotliDecoder::new(Vec::new());
+                decoder.write_all(&compressed).await.unwrap();
+                decoder.shutdown().await.unwrap();
+                decoder.into_inner()
+            }
+        };
+        assert_eq!(decompressed, body);
+    }
+
+    #[tokio::test]
+    async fn gzip_round_trips_and_sets_content_encoding() {
+        assert_round_trips(Compression::Gzip).await;
+        assert_eq!(Compression::Gzip.content_encoding(), Some("gzip"));
+    }
+
+    #[tokio::test]
+    async fn zstd_round_trips_and_sets_content_encoding() {
+        assert_round_trips(Compression::Zstd).await;
+        assert_eq!(Compression::Zstd.content_encoding(), Some("zstd"));
+    }
+
+    #[tokio::test]
+    async fn brotli_round_trips_and_sets_content_encoding() {
+        assert_round_trips(Compression::Brotli).await;
+        assert_eq!(Compression::Brotli.content_encoding(), Some("br"));
+    }
+
+    #[tokio::test]
+    async fn none_passes_body_through_unchanged_with_no_header() {
+        let body = b"unchanged".to_vec();
+        let compressed = Compression::None.compress(body.clone()).await.unwrap();
+        assert_eq!(compressed, body);
+        assert_eq!(Compression::None.content_encoding(), None);
+    }
+
+    #[test]
+    fn bulk_index_clamps_zero_chunk_size_to_one() {
+        let options = BulkOptions {
+            chunk_size: 0,
+            compression: Compression::None,
+        };
+        assert_eq!(options.chunk_size.max(1), 1);
+    }
+
+    #[test]
+    fn bulk_index_splits_docs_at_chunk_size_boundary() {
+        let docs: Vec<Value> = (0..25).map(|i| serde_json::json!({ "i": i })).collect();
+        let chunks: Vec<_> = docs.chunks(10).collect();
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0].len(), 10);
+        assert_eq!(chunks[1].len(), 10);
+        assert_eq!(chunks[2].len(), 5);
+    }
+}
@@ -0,0 +1,139 @@
+use reqwest::StatusCode;
+use std::fmt;
+
+/// Crate-wide error return type. Errors need to cross `spawn_blocking` and
+/// axum handler futures, both of which require `Send`, so every fallible
+/// function in the crate returns this instead of the bare
+/// `Box<dyn std::error::Error>`.
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Stable, machine-readable error codes, modeled after the classic
+/// error-code-table approach: each variant maps to a short string code and
+/// the HTTP status a caller (or a future API layer) should report for it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Code {
+    IndexAlreadyExists,
+    IndexNotFound,
+    IndexCreateFailed,
+    BulkImportFailed,
+    SearchFailed,
+    DatasetParseError,
+    ScrapeFailed,
+}
+
+impl Code {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Code::IndexAlreadyExists => "index_already_exists",
+            Code::IndexNotFound => "index_not_found",
+            Code::IndexCreateFailed => "index_create_failed",
+            Code::BulkImportFailed => "bulk_import_failed",
+            Code::SearchFailed => "search_failed",
+            Code::DatasetParseError => "dataset_parse_error",
+            Code::ScrapeFailed => "scrape_failed",
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Code::IndexAlreadyExists => StatusCode::CONFLICT,
+            Code::IndexNotFound => StatusCode::NOT_FOUND,
+            Code::IndexCreateFailed => StatusCode::BAD_GATEWAY,
+            Code::BulkImportFailed => StatusCode::BAD_REQUEST,
+            Code::SearchFailed => StatusCode::BAD_REQUEST,
+            Code::DatasetParseError => StatusCode::UNPROCESSABLE_ENTITY,
+            Code::ScrapeFailed => StatusCode::BAD_GATEWAY,
+        }
+    }
+
+    /// Maps a backend's own response status for a given operation to the
+    /// most fitting `Code`, falling back to a generic failure code.
+    ///
+    /// Elasticsearch reports an already-existing index as a 400 with a
+    /// `resource_already_exists_exception` body, not a 409 — it never
+    /// returns 409 for index creation. A real 409 is treated the same way
+    /// in case a different backend (or a future ES version) does use it.
+    pub fn from_index_response(status: StatusCode) -> Code {
+        match status {
+            StatusCode::NOT_FOUND => Code::IndexNotFound,
+            StatusCode::CONFLICT | StatusCode::BAD_REQUEST => Code::IndexAlreadyExists,
+            _ => Code::IndexCreateFailed,
+        }
+    }
+
+    pub fn from_bulk_response(status: StatusCode) -> Code {
+        match status {
+            StatusCode::NOT_FOUND => Code::IndexNotFound,
+            _ => Code::BulkImportFailed,
+        }
+    }
+
+    pub fn from_search_response(status: StatusCode) -> Code {
+        match status {
+            StatusCode::NOT_FOUND => Code::IndexNotFound,
+            _ => Code::SearchFailed,
+        }
+    }
+}
+
+/// A structured error carrying a stable `Code`, the HTTP status that `Code`
+/// maps to (see `Code::status`), and a human-readable message. A backend's
+/// own response status appears only in the message.
+#[derive(Debug)]
+pub struct ScrapeError {
+    pub code: Code,
+    pub status: StatusCode,
+    pub message: String,
+}
+
+impl ScrapeError {
+    pub fn new(code: Code, message: impl Into<String>) -> ScrapeError {
+        ScrapeError {
+            status: code.status(),
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ScrapeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}", self.code.as_str(), self.message)
+    }
+}
+
+impl std::error::Error for ScrapeError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn index_response_mapping() {
+        assert_eq!(Code::from_index_response(StatusCode::NOT_FOUND), Code::IndexNotFound);
+        assert_eq!(Code::from_index_response(StatusCode::CONFLICT), Code::IndexAlreadyExists);
+        // Real Elasticsearch reports an already-existing index as 400
+        // (resource_already_exists_exception), never 409.
+        assert_eq!(Code::from_index_response(StatusCode::BAD_REQUEST), Code::IndexAlreadyExists);
+        assert_eq!(
+            Code::from_index_response(StatusCode::INTERNAL_SERVER_ERROR),
+            Code::IndexCreateFailed
+        );
+    }
+
+    #[test]
+    fn bulk_response_mapping() {
+        assert_eq!(Code::from_bulk_response(StatusCode::NOT_FOUND), Code::IndexNotFound);
+        assert_eq!(Code::from_bulk_response(StatusCode::BAD_REQUEST), Code::BulkImportFailed);
+        assert_eq!(
+            Code::from_bulk_response(StatusCode::INTERNAL_SERVER_ERROR),
+            Code::BulkImportFailed
+        );
+    }
+
+    #[test]
+    fn search_response_mapping() {
+        assert_eq!(Code::from_search_response(StatusCode::NOT_FOUND), Code::IndexNotFound);
+        assert_eq!(Code::from_search_response(StatusCode::BAD_REQUEST), Code::SearchFailed);
+    }
+}
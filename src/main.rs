@@ -1,44 +1,189 @@
+mod backend;
+mod error;
+mod scrape_config;
+mod serve;
+mod store;
+
+use backend::{make_backend, BackendKind, BulkOptions, Compression, QueryOptions, SearchBackend};
 use clap::{App, Arg, ArgMatches, SubCommand};
-use log::{error, trace, warn};
-use scraper::{Html, Selector};
-use serde::{Deserialize, Serialize};
+use error::{BoxError, Code, ScrapeError};
+use futures::StreamExt;
+use log::{trace, warn};
+use scrape_config::ScrapeConfig;
 use serde_json::{json, Value};
-use std::io::{BufRead, BufReader, Error, ErrorKind, Write};
+use std::sync::Arc;
+use store::{make_store, DocumentStore, StoreKind};
 use tokio::fs;
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Character {
-    pub name: String,
-    pub portrayal: String,
-    pub description: String,
-}
-
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
+async fn main() -> Result<(), BoxError> {
     pretty_env_logger::init();
     let matches = App::new("Searching Star Wars Characters with Elasticsearch")
         .version("0.1")
         .author("Matthieu Paindavoine <matt@area403.org>")
+        .arg(
+            Arg::with_name("backend")
+                .long("backend")
+                .env("SEARCH_BACKEND")
+                .value_name("BACKEND")
+                .help("Search backend to use: elastic or meili")
+                .default_value("elastic")
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("url")
+                .long("url")
+                .env("SEARCH_BACKEND_URL")
+                .value_name("URL")
+                .help("Base URL of the search backend")
+                .default_value("http://localhost:9200")
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("chunk-size")
+                .long("chunk-size")
+                .value_name("N")
+                .help("Number of documents per bulk-index request")
+                .default_value("1000")
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("compression")
+                .long("compression")
+                .value_name("CODEC")
+                .help("Compress bulk-index request bodies: none, gzip, zstd or brotli")
+                .default_value("none")
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("store")
+                .long("store")
+                .value_name("STORE")
+                .help("Dataset store to use between scraping and indexing: file, sqlite or redis")
+                .default_value("file")
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("store-location")
+                .long("store-location")
+                .value_name("PATH_OR_URL")
+                .help("File path, sqlite file, or redis:// URL for the dataset store")
+                .default_value("dataset.json")
+                .global(true),
+        )
         .subcommand(
             SubCommand::with_name("index")
                 .about("Scrap data and index them in Elasticsearch")
                 .version("0.1")
-                .author("Matthieu Paindavoine <matt@area403.org>"),
+                .author("Matthieu Paindavoine <matt@area403.org>")
+                .arg(
+                    Arg::with_name("url-to-scrape")
+                        .long("scrape-url")
+                        .value_name("URL")
+                        .help("Page to scrape")
+                        .default_value("https://en.wikipedia.org/wiki/List_of_Star_Wars_characters"),
+                )
+                .arg(
+                    Arg::with_name("scrape-config")
+                        .long("scrape-config")
+                        .value_name("FILE")
+                        .help("JSON or TOML file describing the row selector and field mappings")
+                        .default_value("scrape.json"),
+                ),
         )
         .subcommand(SubCommand::with_name("init").about("Initialize Elasticsearch"))
         .subcommand(
-            SubCommand::with_name("search").about("Search").arg(
-                Arg::with_name("query")
-                    .value_name("FEATURE")
-                    .help("Feature to search for"),
-            ),
+            SubCommand::with_name("search")
+                .about("Search")
+                .arg(
+                    Arg::with_name("query")
+                        .value_name("FEATURE")
+                        .help("Feature to search for"),
+                )
+                .arg(
+                    Arg::with_name("limit")
+                        .long("limit")
+                        .value_name("N")
+                        .help("Maximum number of results")
+                        .default_value("10"),
+                )
+                .arg(
+                    Arg::with_name("offset")
+                        .long("offset")
+                        .value_name("N")
+                        .help("Number of results to skip")
+                        .default_value("0"),
+                )
+                .arg(
+                    Arg::with_name("fuzzy")
+                        .long("fuzzy")
+                        .help("Tolerate typos in the query (edit-distance matching)"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("serve")
+                .about("Expose search over HTTP")
+                .arg(
+                    Arg::with_name("addr")
+                        .long("addr")
+                        .value_name("ADDR")
+                        .help("Address to listen on")
+                        .default_value("127.0.0.1:3000"),
+                )
+                .arg(
+                    Arg::with_name("url-to-scrape")
+                        .long("scrape-url")
+                        .value_name("URL")
+                        .help("Page to scrape on POST /index")
+                        .default_value("https://en.wikipedia.org/wiki/List_of_Star_Wars_characters"),
+                )
+                .arg(
+                    Arg::with_name("scrape-config")
+                        .long("scrape-config")
+                        .value_name("FILE")
+                        .help("JSON or TOML file describing the row selector and field mappings")
+                        .default_value("scrape.json"),
+                ),
         )
         .get_matches();
 
+    let bulk_options = BulkOptions {
+        chunk_size: matches
+            .value_of("chunk-size")
+            .expect("has a default")
+            .parse()
+            .unwrap_or(1000),
+        compression: match matches.value_of("compression").expect("has a default") {
+            "gzip" => Compression::Gzip,
+            "zstd" => Compression::Zstd,
+            "brotli" => Compression::Brotli,
+            _ => Compression::None,
+        },
+    };
+    let backend: Arc<dyn SearchBackend> = Arc::from(make_backend(
+        BackendKind::from_str(matches.value_of("backend").expect("has a default")),
+        matches.value_of("url").expect("has a default"),
+        bulk_options,
+    ));
+    let store: Arc<dyn DocumentStore> = Arc::from(make_store(
+        StoreKind::from_str(matches.value_of("store").expect("has a default")),
+        matches.value_of("store-location").expect("has a default"),
+    ));
+
     match matches.subcommand() {
-        ("index", Some(sm)) => index(sm).await,
+        ("index", Some(sm)) => index(sm, backend.as_ref(), store.as_ref()).await,
         ("init", Some(sm)) => init(sm).await,
-        ("search", Some(sm)) => search(sm).await,
+        ("search", Some(sm)) => search(sm, backend.as_ref()).await,
+        ("serve", Some(sm)) => {
+            let addr = sm.value_of("addr").expect("has a default").parse()?;
+            let state = serve::AppState {
+                backend,
+                store,
+                scrape_url: sm.value_of("url-to-scrape").expect("has a default").to_string(),
+                scrape_config: sm.value_of("scrape-config").expect("has a default").to_string(),
+            };
+            serve::serve(addr, state).await
+        }
         _ => {
             warn!("Unrecognized subcommand");
             Err(String::from("foo").into())
@@ -50,199 +195,168 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
 }
 
-async fn index<'a>(_matches: &ArgMatches<'a>) -> Result<(), Box<dyn std::error::Error>> {
-    create_index("starwars").await?;
+async fn index<'a>(
+    matches: &ArgMatches<'a>,
+    backend: &dyn SearchBackend,
+    store: &dyn DocumentStore,
+) -> Result<(), BoxError> {
+    let scrape_url = matches.value_of("url-to-scrape").expect("has a default");
+    let config_path = matches.value_of("scrape-config").expect("has a default");
+    run_index(scrape_url, config_path, backend, store).await
+}
 
-    generate_dataset("https://en.wikipedia.org/wiki/List_of_Star_Wars_characters").await?;
+/// Creates the index, then scrapes `scrape_url` using the mapping declared
+/// in `config_path` into `store`, then bulk-loads whatever `store` holds.
+/// An error from `create_index` (such as an index that already exists)
+/// stops the run before anything is scraped. Shared by the `index`
+/// subcommand and the `serve` subcommand's `POST /index` handler.
+pub(crate) async fn run_index(
+    scrape_url: &str,
+    config_path: &str,
+    backend: &dyn SearchBackend,
+    store: &dyn DocumentStore,
+) -> Result<(), BoxError> {
+    let contents = fs::read_to_string("settings.json").await?;
+    let settings: Value = serde_json::from_str(&contents)
+        .map_err(|e| ScrapeError::new(Code::DatasetParseError, format!("invalid settings.json: {}", e)))?;
+    backend.create_index("starwars", &settings).await?;
 
-    generate_bulk_input("starwars").await?;
+    generate_dataset(scrape_url, config_path, store).await?;
 
-    import_bulk_input("starwars").await?;
+    let docs = store.stream_all().await?.collect::<Vec<_>>().await;
+    backend.bulk_index("starwars", &docs).await?;
 
     Ok(())
 }
 
-async fn init<'a>(_matches: &ArgMatches<'a>) -> Result<(), Box<dyn std::error::Error>> {
+async fn init<'a>(_matches: &ArgMatches<'a>) -> Result<(), BoxError> {
     Ok(())
 }
 
-async fn search<'a>(matches: &ArgMatches<'a>) -> Result<(), Box<dyn std::error::Error>> {
+async fn search<'a>(
+    matches: &ArgMatches<'a>,
+    backend: &dyn SearchBackend,
+) -> Result<(), BoxError> {
     let query = matches.value_of("query").expect("Query parameter");
+    let options = QueryOptions {
+        fuzzy: matches.is_present("fuzzy"),
+        from: matches.value_of("offset").expect("has a default").parse().unwrap_or(0),
+        size: matches.value_of("limit").expect("has a default").parse().unwrap_or(10),
+        boosts: None,
+    };
 
-    let res = search_query("starwars", query).await?;
+    let res = backend.search("starwars", query, &options).await?;
 
     println!("{}", res.to_string());
 
     Ok(())
 }
 
-async fn generate_dataset(url: &str) -> Result<(), Box<dyn std::error::Error>> {
+async fn generate_dataset(
+    url: &str,
+    config_path: &str,
+    store: &dyn DocumentStore,
+) -> Result<(), BoxError> {
+    trace!("Loading scrape config from {}", config_path);
+    let config_contents = fs::read_to_string(config_path).await?;
+    let config = if config_path.ends_with(".toml") {
+        ScrapeConfig::from_toml(&config_contents)?
+    } else {
+        ScrapeConfig::from_json(&config_contents)?
+    };
+
     trace!("Creating dataset from {}", url);
-    let body = reqwest::get(url).await?.text().await?;
-
-    let fragment = Html::parse_document(&body);
-
-    let rows_selector = Selector::parse("table.wikitable > tbody > tr").unwrap();
-    let cells_selector = Selector::parse("td").unwrap();
-
-    // iterate over elements matching our selector
-    let characters = fragment
-        .select(&rows_selector)
-        .map(|row| {
-            let dat = row
-                .select(&cells_selector)
-                .map(|cell| cell.text().collect::<Vec<_>>().join(""))
-                .map(|mut t| {
-                    t.pop(); // remove trailing \n
-                    t
-                })
-                .collect::<Vec<_>>();
-
-            if dat.len() == 3 {
-                Ok(Character {
-                    name: dat[0].clone(),
-                    portrayal: dat[1].clone(),
-                    description: dat[2].clone(),
-                })
-            } else {
-                Err(Error::new(ErrorKind::Other, "oh no!"))
-            }
-        })
-        .filter_map(|rc| rc.ok())
-        .collect::<Vec<_>>();
+    let resp = reqwest::get(url).await?;
+    if !resp.status().is_success() {
+        return Err(Box::new(ScrapeError::new(
+            Code::ScrapeFailed,
+            format!("fetching {} failed: status {}", url, resp.status()),
+        )));
+    }
+    let body = resp.text().await?;
 
-    trace!("Writing dataset to 'dataset.json'");
-    let mut file = std::fs::File::create("dataset.json")?;
+    let docs = scrape_config::scrape(&body, &config)?
+        .into_iter()
+        .map(Value::Object)
+        .collect::<Vec<_>>();
 
-    serde_json::to_writer_pretty(&mut file, &characters)?;
-    trace!("Dataset dataset.json successfully created");
+    store.put_all(&docs).await?;
+    trace!("Dataset successfully written to the document store");
 
     Ok(())
 }
 
-async fn create_index(name: &str) -> Result<(), Box<dyn std::error::Error>> {
-    let endpoint = format!("http://localhost:9200/{}", name);
-    trace!("Creating index {}", endpoint);
-    let contents = fs::read_to_string("settings.json").await?;
-    let settings: Value = serde_json::from_str(&contents)?;
-    let client = reqwest::Client::new();
-    let resp = client
-        .put(&endpoint)
-        .header("Content-Type", "application/json")
-        .json(&settings)
-        .send()
-        .await?;
-    if resp.status().is_success() {
-        trace!("Index {} successfully created", endpoint);
-        Ok(())
-    } else {
-        let resp_status = String::from(resp.status().as_str());
-        let resp_msg = resp.text().await.expect("Response");
-        error!(
-            "Index '{}' creation failed with status {}: {}",
-            name, resp_status, resp_msg
-        );
-        Err(Box::new(Error::new(
-            ErrorKind::Other,
-            format!("Index '{}' failure: status {}", name, resp_status),
-        )))
+pub(crate) fn build_query(query: &str, options: &QueryOptions) -> Result<Value, BoxError> {
+    let fields = match &options.boosts {
+        Some(boosts) => boosts
+            .iter()
+            .map(|(field, boost)| format!("{}^{}", field, boost))
+            .collect::<Vec<_>>(),
+        None => vec![String::from("name^10"), String::from("description")],
+    };
+
+    let mut multi_match = json!({
+        "query": query,
+        "fields": fields,
+    });
+    if options.fuzzy {
+        multi_match["fuzziness"] = json!("AUTO");
     }
-}
 
-async fn generate_bulk_input(name: &str) -> Result<(), Box<dyn std::error::Error>> {
-    trace!("Creating bulk input 'bulk.json'");
-    let mut file = std::fs::File::create("bulk.json")?;
-    let contents = fs::read_to_string("dataset.json").await?;
-    let value: Value = serde_json::from_str(&contents)?;
-    let values: &Vec<Value> = value.as_array().expect("dataset should be a JSON array");
-    values.iter().for_each(|value| {
-        let id = uuid::Uuid::new_v4();
-        let json = format!(
-            "{{ \"index\": {{ \"_index\": \"{}\", \"_type\": \"_doc\", \"_id\": \"{}\" }} }}\n",
-            name, id
-        );
-        file.write_all(json.as_bytes()).unwrap();
-        serde_json::to_writer(&mut file, &value).expect("could not write bulk");
-        file.write_all("\n".as_bytes()).unwrap();
+    let json = json!({
+        "query": { "multi_match": multi_match },
+        "from": options.from,
+        "size": options.size,
     });
-    trace!("Bulk input 'bulk.json' successfully created");
-    Ok(())
+    Ok(json)
 }
 
-async fn import_bulk_input(name: &str) -> Result<(), Box<dyn std::error::Error>> {
-    let endpoint = format!("http://localhost:9200/{}/_doc/_bulk", name);
-    trace!("Importing bulk dataset to {}", endpoint);
-    let file = std::fs::File::open("bulk.json").expect("no such file");
-    let buf = BufReader::new(file);
-    let chunks = buf
-        .lines()
-        .map(|l| {
-            l.map(|mut z| {
-                z.push('\n');
-                z
-            })
-        })
-        .collect::<Vec<Result<String, _>>>();
-    let stream = futures::stream::iter(chunks);
-    let body = reqwest::Body::wrap_stream(stream);
-    let client = reqwest::Client::new();
-    let resp = client
-        .put(&endpoint)
-        .body(body)
-        .header("Content-Type", "application/json")
-        .send()
-        .await?;
-    if resp.status().is_success() {
-        trace!("Dataset successfully imported");
-        Ok(())
-    } else {
-        let resp_status = String::from(resp.status().as_str());
-        let resp_msg = resp.text().await.expect("Response");
-        error!(
-            "Bulk import {} failed with status {}: {}",
-            name, resp_status, resp_msg
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_query_defaults_to_name_and_description_fields() {
+        let options = QueryOptions::default();
+        let query = build_query("luke", &options).unwrap();
+        assert_eq!(
+            query["query"]["multi_match"]["fields"],
+            json!(["name^10", "description"])
         );
-        Err(Box::new(Error::new(
-            ErrorKind::Other,
-            format!("Bulk import {} failure: status {}", name, resp_status),
-        )))
+        assert!(query["query"]["multi_match"].get("fuzziness").is_none());
     }
-}
 
-async fn search_query(name: &str, query: &str) -> Result<Value, Box<dyn std::error::Error>> {
-    let endpoint = format!("http://localhost:9200/{}/_search", name);
-    trace!("Searching endpoint {}", endpoint);
-    let json = build_query(query)?;
-    let client = reqwest::Client::new();
-    let resp = client.get(&endpoint).json(&json).send().await?;
-    if resp.status().is_success() {
-        trace!("Dataset successfulyl searched");
-        let ret = resp.json::<Value>().await?;
-        Ok(ret)
-    } else {
-        let resp_status = String::from(resp.status().as_str());
-        let resp_msg = resp.text().await.expect("Response");
-        error!(
-            "Dataset search failed with status {}: {}",
-            resp_status, resp_msg
-        );
-        Err(Box::new(Error::new(
-            ErrorKind::Other,
-            format!("Dataset search failed: status {}: {}", name, resp_status),
-        )))
+    #[test]
+    fn build_query_sets_fuzziness_when_requested() {
+        let options = QueryOptions {
+            fuzzy: true,
+            ..QueryOptions::default()
+        };
+        let query = build_query("luke", &options).unwrap();
+        assert_eq!(query["query"]["multi_match"]["fuzziness"], json!("AUTO"));
+    }
+
+    #[test]
+    fn build_query_carries_pagination_through() {
+        let options = QueryOptions {
+            from: 20,
+            size: 5,
+            ..QueryOptions::default()
+        };
+        let query = build_query("luke", &options).unwrap();
+        assert_eq!(query["from"], json!(20));
+        assert_eq!(query["size"], json!(5));
+    }
+
+    #[test]
+    fn build_query_uses_boosts_when_given() {
+        let mut boosts = std::collections::HashMap::new();
+        boosts.insert("name".to_string(), 5.0);
+        let options = QueryOptions {
+            boosts: Some(boosts),
+            ..QueryOptions::default()
+        };
+        let query = build_query("luke", &options).unwrap();
+        assert_eq!(query["query"]["multi_match"]["fields"], json!(["name^5"]));
     }
-}
-fn build_query(query: &str) -> Result<Value, Box<dyn std::error::Error>> {
-    let json = json!({
-        "query": {
-            "multi_match": {
-                "query": query,
-                "fields": [
-                    "name^10",
-                    "description"
-                ]
-            }
-        }
-    });
-    Ok(json)
 }
@@ -0,0 +1,239 @@
+use log::trace;
+use scraper::{ElementRef, Html, Selector};
+use serde::de::{self, Deserializer};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::error::{BoxError, Code, ScrapeError};
+
+/// How to turn a matched element into a JSON value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Extraction {
+    /// The element's text content, trimmed of a single trailing newline
+    /// the way the previous Wikipedia-specific scraper did.
+    Text,
+    /// The value of a named attribute on the element.
+    Attr(String),
+    /// The element's inner HTML.
+    Html,
+}
+
+impl<'de> Deserialize<'de> for Extraction {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        match s.as_str() {
+            "text" => Ok(Extraction::Text),
+            "html" => Ok(Extraction::Html),
+            _ => match s.strip_prefix("attr:") {
+                Some(attr) => Ok(Extraction::Attr(attr.to_string())),
+                None => Err(de::Error::custom(format!(
+                    "unknown extraction mode '{}', expected 'text', 'html' or 'attr:<name>'",
+                    s
+                ))),
+            },
+        }
+    }
+}
+
+impl Default for Extraction {
+    fn default() -> Self {
+        Extraction::Text
+    }
+}
+
+/// What to do with a row that doesn't produce a value for every required field.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OnMissing {
+    Skip,
+    Error,
+}
+
+impl Default for OnMissing {
+    fn default() -> Self {
+        OnMissing::Skip
+    }
+}
+
+/// A single field to pull out of each matched row.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FieldMapping {
+    pub name: String,
+    pub selector: String,
+    #[serde(default)]
+    pub extract: Extraction,
+    #[serde(default)]
+    pub required: bool,
+}
+
+/// Declarative description of how to turn a page into documents: a
+/// selector for the repeating rows, and a field mapping relative to each
+/// row, replacing the previous hardcoded `table.wikitable` / `Character`
+/// scraper.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScrapeConfig {
+    pub row_selector: String,
+    pub fields: Vec<FieldMapping>,
+    #[serde(default)]
+    pub on_missing: OnMissing,
+}
+
+impl ScrapeConfig {
+    pub fn from_json(contents: &str) -> Result<ScrapeConfig, BoxError> {
+        let config: ScrapeConfig = serde_json::from_str(contents)
+            .map_err(|e| ScrapeError::new(Code::DatasetParseError, format!("invalid scrape config: {}", e)))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn from_toml(contents: &str) -> Result<ScrapeConfig, BoxError> {
+        let config: ScrapeConfig = toml::from_str(contents)
+            .map_err(|e| ScrapeError::new(Code::DatasetParseError, format!("invalid scrape config: {}", e)))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Parses every CSS selector up front so a typo in the config is
+    /// reported once, clearly, instead of being swallowed row by row
+    /// inside `extract` as a generic "field didn't match".
+    fn validate(&self) -> Result<(), BoxError> {
+        compile_selectors(self)?;
+        Ok(())
+    }
+}
+
+fn parse_selector(selector: &str, field_name: Option<&str>) -> Result<Selector, BoxError> {
+    Selector::parse(selector).map_err(|e| {
+        let message = match field_name {
+            Some(name) => format!("invalid selector '{}' for field '{}': {:?}", selector, name, e),
+            None => format!("invalid row_selector '{}': {:?}", selector, e),
+        };
+        Box::new(ScrapeError::new(Code::DatasetParseError, message))
+    })
+}
+
+/// Parses `config`'s `row_selector` and every field's selector exactly
+/// once, so neither `validate` nor `scrape` has to reparse a selector
+/// string per row.
+fn compile_selectors(config: &ScrapeConfig) -> Result<(Selector, Vec<Selector>), BoxError> {
+    let row_selector = parse_selector(&config.row_selector, None)?;
+    let field_selectors = config
+        .fields
+        .iter()
+        .map(|field| parse_selector(&field.selector, Some(&field.name)))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((row_selector, field_selectors))
+}
+
+fn extract(element: ElementRef, selector: &Selector, extraction: &Extraction) -> Option<Value> {
+    let target = element.select(selector).next()?;
+    match extraction {
+        Extraction::Text => {
+            let mut text = target.text().collect::<Vec<_>>().join("");
+            if text.ends_with('\n') {
+                text.pop();
+            }
+            Some(Value::String(text))
+        }
+        Extraction::Attr(attr) => target.value().attr(attr).map(|v| Value::String(v.to_string())),
+        Extraction::Html => Some(Value::String(target.inner_html())),
+    }
+}
+
+/// Scrapes `body` according to `config`, emitting one generic JSON object
+/// per matched row instead of a fixed `Character` struct.
+pub fn scrape(body: &str, config: &ScrapeConfig) -> Result<Vec<Map<String, Value>>, BoxError> {
+    let fragment = Html::parse_document(body);
+    let (row_selector, field_selectors) = compile_selectors(config)?;
+
+    let mut docs = Vec::new();
+    for row in fragment.select(&row_selector) {
+        let mut doc = Map::new();
+        let mut missing_required = false;
+        for (mapping, selector) in config.fields.iter().zip(&field_selectors) {
+            match extract(row, selector, &mapping.extract) {
+                Some(value) => {
+                    doc.insert(mapping.name.clone(), value);
+                }
+                None if mapping.required => {
+                    missing_required = true;
+                    break;
+                }
+                None => {}
+            }
+        }
+
+        if missing_required {
+            match config.on_missing {
+                OnMissing::Skip => {
+                    trace!("Skipping row missing a required field");
+                    continue;
+                }
+                OnMissing::Error => {
+                    return Err(Box::new(ScrapeError::new(
+                        Code::ScrapeFailed,
+                        "row missing a required field",
+                    )));
+                }
+            }
+        }
+
+        docs.push(doc);
+    }
+
+    Ok(docs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(name: &str, selector: &str, required: bool) -> FieldMapping {
+        FieldMapping {
+            name: name.to_string(),
+            selector: selector.to_string(),
+            extract: Extraction::Text,
+            required,
+        }
+    }
+
+    #[test]
+    fn from_json_rejects_invalid_selector() {
+        let json = r#"{
+            "row_selector": "tr",
+            "fields": [{"name": "name", "selector": "[[["}]
+        }"#;
+        let err = ScrapeConfig::from_json(json).unwrap_err();
+        let err = err.downcast_ref::<ScrapeError>().expect("ScrapeError");
+        assert_eq!(err.code, Code::DatasetParseError);
+    }
+
+    #[test]
+    fn scrape_skips_rows_missing_required_field_by_default() {
+        let config = ScrapeConfig {
+            row_selector: "tr".to_string(),
+            fields: vec![field("name", ".name", true)],
+            on_missing: OnMissing::default(),
+        };
+        let body = "<table><tr><td class='name'>Luke</td></tr><tr><td>no name here</td></tr></table>";
+        let docs = scrape(body, &config).unwrap();
+        assert_eq!(docs.len(), 1);
+        assert_eq!(docs[0]["name"], Value::String("Luke".to_string()));
+    }
+
+    #[test]
+    fn scrape_errors_on_missing_required_field_when_configured() {
+        let config = ScrapeConfig {
+            row_selector: "tr".to_string(),
+            fields: vec![field("name", ".name", true)],
+            on_missing: OnMissing::Error,
+        };
+        let body = "<table><tr><td>no name here</td></tr></table>";
+        let err = scrape(body, &config).unwrap_err();
+        let err = err.downcast_ref::<ScrapeError>().expect("ScrapeError");
+        assert_eq!(err.code, Code::ScrapeFailed);
+    }
+}
@@ -0,0 +1,98 @@
+use crate::backend::{QueryOptions, SearchBackend};
+use crate::error::{BoxError, ScrapeError};
+use crate::run_index;
+use crate::store::DocumentStore;
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use log::info;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+/// State shared across every request: a single long-lived `reqwest::Client`
+/// (via the backend) rather than one per call, plus what's needed to
+/// re-run the scrape-and-index pipeline on demand.
+#[derive(Clone)]
+pub struct AppState {
+    pub backend: Arc<dyn SearchBackend>,
+    pub store: Arc<dyn DocumentStore>,
+    pub scrape_url: String,
+    pub scrape_config: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchParams {
+    q: String,
+    #[serde(default)]
+    limit: Option<usize>,
+    #[serde(default)]
+    offset: Option<usize>,
+    #[serde(default)]
+    fuzzy: Option<bool>,
+}
+
+/// Wraps any error so the structured `ScrapeError` codes surface as the
+/// matching HTTP status, with other errors falling back to a 500.
+struct ApiError(BoxError);
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = self
+            .0
+            .downcast_ref::<ScrapeError>()
+            .map(|e| e.status)
+            .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        let body = Json(json!({ "error": self.0.to_string() }));
+        (status, body).into_response()
+    }
+}
+
+impl From<BoxError> for ApiError {
+    fn from(err: BoxError) -> Self {
+        ApiError(err)
+    }
+}
+
+async fn search_handler(
+    State(state): State<AppState>,
+    Query(params): Query<SearchParams>,
+) -> Result<Json<Value>, ApiError> {
+    let options = QueryOptions {
+        fuzzy: params.fuzzy.unwrap_or(false),
+        from: params.offset.unwrap_or(0),
+        size: params.limit.unwrap_or(10),
+        boosts: None,
+    };
+    let res = state.backend.search("starwars", &params.q, &options).await?;
+    Ok(Json(res))
+}
+
+async fn index_handler(State(state): State<AppState>) -> Result<StatusCode, ApiError> {
+    run_index(
+        &state.scrape_url,
+        &state.scrape_config,
+        state.backend.as_ref(),
+        state.store.as_ref(),
+    )
+    .await?;
+    Ok(StatusCode::ACCEPTED)
+}
+
+fn app(state: AppState) -> Router {
+    Router::new()
+        .route("/search", get(search_handler))
+        .route("/index", post(index_handler))
+        .with_state(state)
+}
+
+pub async fn serve(addr: SocketAddr, state: AppState) -> Result<(), BoxError> {
+    info!("Listening on {}", addr);
+    axum::Server::bind(&addr)
+        .serve(app(state).into_make_service())
+        .await?;
+    Ok(())
+}
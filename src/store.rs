@@ -0,0 +1,221 @@
+use crate::error::BoxError;
+use futures::stream::{self, BoxStream};
+use futures::StreamExt;
+use log::trace;
+use serde_json::Value;
+use std::path::PathBuf;
+
+/// Persists the scraped dataset between the scrape stage and the index
+/// stage, independently of either, in place of the previous hardcoded
+/// `dataset.json` file. `put_all` replaces whatever the store held;
+/// documents are not updated one at a time. `run_index` scrapes into the
+/// store before every bulk load.
+#[async_trait::async_trait]
+pub trait DocumentStore: Send + Sync {
+    async fn put_all(&self, docs: &[Value]) -> Result<(), BoxError>;
+    async fn stream_all(&self) -> Result<BoxStream<'static, Value>, BoxError>;
+}
+
+/// The original behavior: the dataset lives in a single JSON file.
+pub struct FileStore {
+    pub path: PathBuf,
+}
+
+impl FileStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        FileStore { path: path.into() }
+    }
+}
+
+#[async_trait::async_trait]
+impl DocumentStore for FileStore {
+    async fn put_all(&self, docs: &[Value]) -> Result<(), BoxError> {
+        trace!("Writing dataset to '{}'", self.path.display());
+        let mut file = std::fs::File::create(&self.path)?;
+        serde_json::to_writer_pretty(&mut file, docs)?;
+        Ok(())
+    }
+
+    async fn stream_all(&self) -> Result<BoxStream<'static, Value>, BoxError> {
+        trace!("Reading dataset from '{}'", self.path.display());
+        let contents = tokio::fs::read_to_string(&self.path).await?;
+        let docs: Vec<Value> = serde_json::from_str(&contents)?;
+        Ok(stream::iter(docs).boxed())
+    }
+}
+
+/// Keeps the dataset in a SQLite table, one row per document, selected
+/// with `--store sqlite`; `path` names the database file.
+pub struct SqliteStore {
+    pub path: PathBuf,
+}
+
+impl SqliteStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        SqliteStore { path: path.into() }
+    }
+}
+
+#[async_trait::async_trait]
+impl DocumentStore for SqliteStore {
+    async fn put_all(&self, docs: &[Value]) -> Result<(), BoxError> {
+        trace!("Writing {} documents to sqlite '{}'", docs.len(), self.path.display());
+        let path = self.path.clone();
+        let payload = docs
+            .iter()
+            .map(serde_json::to_string)
+            .collect::<Result<Vec<_>, _>>()?;
+        tokio::task::spawn_blocking(move || -> Result<(), BoxError> {
+            let conn = rusqlite::Connection::open(path)?;
+            conn.execute(
+                "CREATE TABLE IF NOT EXISTS documents (id INTEGER PRIMARY KEY, doc TEXT NOT NULL)",
+                [],
+            )?;
+            conn.execute("DELETE FROM documents", [])?;
+            for doc in payload {
+                conn.execute("INSERT INTO documents (doc) VALUES (?1)", [doc])?;
+            }
+            Ok(())
+        })
+        .await??;
+        Ok(())
+    }
+
+    async fn stream_all(&self) -> Result<BoxStream<'static, Value>, BoxError> {
+        trace!("Reading documents from sqlite '{}'", self.path.display());
+        let path = self.path.clone();
+        let docs = tokio::task::spawn_blocking(
+            move || -> Result<Vec<Value>, BoxError> {
+                let conn = rusqlite::Connection::open(path)?;
+                let mut stmt = conn.prepare("SELECT doc FROM documents ORDER BY id")?;
+                let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+                let mut docs = Vec::new();
+                for row in rows {
+                    docs.push(serde_json::from_str(&row?)?);
+                }
+                Ok(docs)
+            },
+        )
+        .await??;
+        Ok(stream::iter(docs).boxed())
+    }
+}
+
+/// Keeps the dataset in a Redis list, selected with `--store redis`; `url`
+/// is the `redis://` connection string and `key` names the list.
+pub struct RedisStore {
+    pub url: String,
+    pub key: String,
+}
+
+impl RedisStore {
+    pub fn new(url: impl Into<String>, key: impl Into<String>) -> Self {
+        RedisStore {
+            url: url.into(),
+            key: key.into(),
+        }
+    }
+
+    async fn connection(&self) -> Result<redis::aio::MultiplexedConnection, BoxError> {
+        let client = redis::Client::open(self.url.as_str())?;
+        Ok(client.get_multiplexed_tokio_connection().await?)
+    }
+}
+
+#[async_trait::async_trait]
+impl DocumentStore for RedisStore {
+    async fn put_all(&self, docs: &[Value]) -> Result<(), BoxError> {
+        use redis::AsyncCommands;
+        trace!("Writing {} documents to redis key '{}'", docs.len(), self.key);
+        let mut conn = self.connection().await?;
+        let _: () = conn.del(&self.key).await?;
+        for doc in docs {
+            let _: () = conn.rpush(&self.key, serde_json::to_string(doc)?).await?;
+        }
+        Ok(())
+    }
+
+    async fn stream_all(&self) -> Result<BoxStream<'static, Value>, BoxError> {
+        use redis::AsyncCommands;
+        trace!("Reading documents from redis key '{}'", self.key);
+        let mut conn = self.connection().await?;
+        let raw: Vec<String> = conn.lrange(&self.key, 0, -1).await?;
+        let docs = raw
+            .into_iter()
+            .map(|s| serde_json::from_str(&s))
+            .collect::<Result<Vec<Value>, _>>()?;
+        Ok(stream::iter(docs).boxed())
+    }
+}
+
+/// Which store to use, selected via the `--store` flag.
+pub enum StoreKind {
+    File,
+    Sqlite,
+    Redis,
+}
+
+impl StoreKind {
+    pub fn from_str(s: &str) -> StoreKind {
+        match s {
+            "sqlite" => StoreKind::Sqlite,
+            "redis" => StoreKind::Redis,
+            _ => StoreKind::File,
+        }
+    }
+}
+
+pub fn make_store(kind: StoreKind, location: &str) -> Box<dyn DocumentStore> {
+    match kind {
+        StoreKind::File => Box::new(FileStore::new(location)),
+        StoreKind::Sqlite => Box::new(SqliteStore::new(location)),
+        StoreKind::Redis => Box::new(RedisStore::new(location, "starwars:dataset")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn temp_path(suffix: &str) -> PathBuf {
+        let mut path = std::env::temp_dir();
+        path.push(format!("test-scraping-{}-{}", std::process::id(), suffix));
+        path
+    }
+
+    #[test]
+    fn store_kind_from_str_recognizes_known_names_and_defaults_to_file() {
+        assert!(matches!(StoreKind::from_str("sqlite"), StoreKind::Sqlite));
+        assert!(matches!(StoreKind::from_str("redis"), StoreKind::Redis));
+        assert!(matches!(StoreKind::from_str("file"), StoreKind::File));
+        assert!(matches!(StoreKind::from_str("anything-else"), StoreKind::File));
+    }
+
+    #[tokio::test]
+    async fn file_store_round_trips_documents() {
+        let path = temp_path("file-store.json");
+        let store = FileStore::new(&path);
+        let docs = vec![json!({ "name": "Luke" }), json!({ "name": "Leia" })];
+
+        store.put_all(&docs).await.unwrap();
+        let roundtripped: Vec<Value> = store.stream_all().await.unwrap().collect().await;
+
+        std::fs::remove_file(&path).ok();
+        assert_eq!(roundtripped, docs);
+    }
+
+    #[tokio::test]
+    async fn sqlite_store_round_trips_documents() {
+        let path = temp_path("store.sqlite");
+        std::fs::remove_file(&path).ok();
+        let store = SqliteStore::new(&path);
+        let docs = vec![json!({ "name": "Luke" }), json!({ "name": "Leia" })];
+
+        store.put_all(&docs).await.unwrap();
+        let roundtripped: Vec<Value> = store.stream_all().await.unwrap().collect().await;
+
+        std::fs::remove_file(&path).ok();
+        assert_eq!(roundtripped, docs);
+    }
+}